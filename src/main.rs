@@ -1,24 +1,344 @@
 extern crate tokio;
-use hyper::{Client, StatusCode, Uri};
-use serde::Deserialize;
-use std::{error::Error, fs, time::Duration};
-use teloxide::{prelude::*, types::ChatId};
-use tokio::time::sleep;
+use hyper::client::connect::Connect;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{body, Body, Client, Request, Response, Server, StatusCode, Uri};
+use hyper_socks2::{Auth, SocksConnector};
+use rand::SeedableRng;
+use rand_distr::{Distribution as _, Exp, Uniform};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    error::Error,
+    fmt::Write as _,
+    fs,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use teloxide::{
+    dispatching::{Dispatcher, UpdateFilterExt},
+    prelude::*,
+    types::ChatId,
+    utils::command::BotCommands,
+};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
 use tracing::{error, info};
 use tracing_subscriber::prelude::*;
 
 const CONFIG_ENV: &str = "HEALTHCHECK_CONFIG";
 const CONFIG_VAL: &str = "healthcheck.toml";
 
+// Special trigger target meaning "probe every address now".
+const TRIGGER_ALL: &str = "*";
+
+#[derive(Deserialize, Clone)]
+struct ProxyConfig {
+    address: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum ExpectedStatus {
+    Code(u16),
+    Codes(Vec<u16>),
+    Range { min: u16, max: u16 },
+}
+
+impl ExpectedStatus {
+    fn matches(&self, status: StatusCode) -> bool {
+        let status = status.as_u16();
+        match self {
+            ExpectedStatus::Code(code) => status == *code,
+            ExpectedStatus::Codes(codes) => codes.contains(&status),
+            ExpectedStatus::Range { min, max } => status >= *min && status <= *max,
+        }
+    }
+}
+
+impl Default for ExpectedStatus {
+    fn default() -> Self {
+        ExpectedStatus::Code(StatusCode::OK.as_u16())
+    }
+}
+
+#[cfg(test)]
+mod expected_status_tests {
+    use super::*;
+
+    #[test]
+    fn code_matches_only_that_status() {
+        let expected = ExpectedStatus::Code(200);
+        assert!(expected.matches(StatusCode::OK));
+        assert!(!expected.matches(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn codes_matches_any_listed_status() {
+        let expected = ExpectedStatus::Codes(vec![200, 201, 204]);
+        assert!(expected.matches(StatusCode::CREATED));
+        assert!(!expected.matches(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn range_matches_inclusive_bounds() {
+        let expected = ExpectedStatus::Range { min: 200, max: 299 };
+        assert!(expected.matches(StatusCode::OK));
+        assert!(expected.matches(StatusCode::from_u16(299).unwrap()));
+        assert!(!expected.matches(StatusCode::from_u16(300).unwrap()));
+    }
+
+    #[test]
+    fn default_is_200() {
+        assert!(ExpectedStatus::default().matches(StatusCode::OK));
+    }
+}
+
+// Either a fixed interval in milliseconds, or a distribution to draw a fresh
+// one from every iteration. A fixed value is just a degenerate distribution.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum IntervalSpec {
+    Fixed(u64),
+    Distribution(DistributionSpec),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "dist", rename_all = "lowercase")]
+enum DistributionSpec {
+    Uniform { min: u64, max: u64 },
+    Exponential { mean: u64 },
+}
+
+impl IntervalSpec {
+    fn sample(&self, rng: &mut Xoshiro256PlusPlus) -> Duration {
+        let millis = match self {
+            IntervalSpec::Fixed(millis) => *millis,
+            IntervalSpec::Distribution(DistributionSpec::Uniform { min, max }) => {
+                Uniform::new_inclusive(min, max).sample(rng)
+            }
+            IntervalSpec::Distribution(DistributionSpec::Exponential { mean }) => {
+                Exp::new(1.0 / *mean as f64)
+                    .expect("mean must be positive")
+                    .sample(rng) as u64
+            }
+        };
+        Duration::from_millis(millis)
+    }
+
+    // Validates the spec once, at config-resolution time, so a bad interval
+    // fails startup with a clear message instead of panicking deep inside a
+    // spawned `check` task on its first `sample` call.
+    fn validate(&self) {
+        if let IntervalSpec::Distribution(dist) = self {
+            dist.validate();
+        }
+    }
+}
+
+impl DistributionSpec {
+    fn validate(&self) {
+        match self {
+            DistributionSpec::Uniform { min, max } => {
+                assert!(
+                    min <= max,
+                    "uniform interval requires min <= max (got min={}, max={})",
+                    min,
+                    max
+                );
+            }
+            DistributionSpec::Exponential { mean } => {
+                assert!(*mean > 0, "exponential interval requires mean > 0");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod interval_spec_tests {
+    use super::*;
+
+    #[test]
+    fn fixed_samples_itself() {
+        let spec = IntervalSpec::Fixed(500);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        assert_eq!(spec.sample(&mut rng), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn uniform_samples_within_bounds() {
+        let spec = IntervalSpec::Distribution(DistributionSpec::Uniform { min: 100, max: 200 });
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        for _ in 0..100 {
+            let sampled = spec.sample(&mut rng).as_millis() as u64;
+            assert!((100..=200).contains(&sampled));
+        }
+    }
+
+    #[test]
+    fn fixed_validate_is_a_no_op() {
+        IntervalSpec::Fixed(0).validate();
+    }
+
+    #[test]
+    #[should_panic(expected = "uniform interval requires min <= max")]
+    fn uniform_validate_rejects_min_above_max() {
+        IntervalSpec::Distribution(DistributionSpec::Uniform { min: 10, max: 5 }).validate();
+    }
+
+    #[test]
+    #[should_panic(expected = "exponential interval requires mean > 0")]
+    fn exponential_validate_rejects_zero_mean() {
+        IntervalSpec::Distribution(DistributionSpec::Exponential { mean: 0 }).validate();
+    }
+}
+
+#[derive(Deserialize, Clone)]
+struct AddressConfig {
+    url: String,
+    #[serde(default)]
+    expected_status: ExpectedStatus,
+    // Matched as a regex against the response body, not a literal substring,
+    // so config authors can anchor or use character classes (e.g. `^ok$`).
+    body_match: Option<String>,
+    check_interval_success: Option<IntervalSpec>,
+    check_interval_fail: Option<IntervalSpec>,
+    notify_failures: Option<u64>,
+    timeout: Option<u64>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum AddressEntry {
+    Simple(String),
+    Detailed(AddressConfig),
+}
+
+impl AddressEntry {
+    fn into_config(self) -> AddressConfig {
+        match self {
+            AddressEntry::Simple(url) => AddressConfig {
+                url,
+                expected_status: ExpectedStatus::default(),
+                body_match: None,
+                check_interval_success: None,
+                check_interval_fail: None,
+                notify_failures: None,
+                timeout: None,
+            },
+            AddressEntry::Detailed(config) => config,
+        }
+    }
+}
+
 #[derive(Deserialize, Clone)]
 struct Config {
     telegram_token: String,
     telegram_chat_id: i64,
-    check_interval_success: u64,
-    check_interval_fail: u64,
+    check_interval_success: IntervalSpec,
+    check_interval_fail: IntervalSpec,
     notify_failures: u64,
     rereport: u64,
-    addresses: Vec<String>,
+    addresses: Vec<AddressEntry>,
+    #[serde(default)]
+    proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    listen: Option<String>,
+}
+
+// Fully resolved per-address settings, with global defaults already applied.
+struct AddressCheck {
+    url: String,
+    expected_status: ExpectedStatus,
+    body_match: Option<Regex>,
+    check_interval_success: IntervalSpec,
+    check_interval_fail: IntervalSpec,
+    notify_failures: u64,
+    timeout: Option<Duration>,
+    rng: Xoshiro256PlusPlus,
+}
+
+impl AddressCheck {
+    fn from_config(address: AddressConfig, config: &Config) -> Self {
+        let check_interval_success = address
+            .check_interval_success
+            .unwrap_or_else(|| config.check_interval_success.clone());
+        let check_interval_fail = address
+            .check_interval_fail
+            .unwrap_or_else(|| config.check_interval_fail.clone());
+        check_interval_success.validate();
+        check_interval_fail.validate();
+        assert!(
+            config.rereport > 0,
+            "rereport must be greater than 0 (fail_in_row % rereport would panic on the first failure)"
+        );
+
+        AddressCheck {
+            url: address.url,
+            expected_status: address.expected_status,
+            body_match: address
+                .body_match
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .expect("body_match must be a valid regex"),
+            check_interval_success,
+            check_interval_fail,
+            notify_failures: address.notify_failures.unwrap_or(config.notify_failures),
+            timeout: address.timeout.map(Duration::from_millis),
+            rng: Xoshiro256PlusPlus::from_entropy(),
+        }
+    }
+}
+
+// Live up/down state and counters for one address, shared between the
+// checker loop and the Telegram command handler.
+#[derive(Clone, Default, Serialize)]
+struct CheckState {
+    up: bool,
+    fail_in_row: u64,
+    number_of_fail: u64,
+    number_of_success: u64,
+    last_latency_ms: Option<u64>,
+}
+
+type SharedState = Arc<Mutex<HashMap<String, CheckState>>>;
+
+// Tracks temporary notification suppression, either globally or per address.
+#[derive(Default)]
+struct MuteState {
+    global_until: Option<Instant>,
+    addresses: HashMap<String, Instant>,
+}
+
+impl MuteState {
+    fn is_muted(&self, url: &str) -> bool {
+        let now = Instant::now();
+        self.global_until.map_or(false, |until| now < until)
+            || self.addresses.get(url).map_or(false, |until| now < *until)
+    }
+}
+
+type SharedMute = Arc<Mutex<MuteState>>;
+
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "lowercase", description = "Supported commands:")]
+enum Command {
+    #[command(description = "show up/down state and counters for every address")]
+    Status,
+    #[command(
+        description = "mute notifications: /mute <duration> or /mute <url> <duration>, e.g. /mute 30m"
+    )]
+    Mute(String),
+    #[command(description = "force an immediate probe of <url>")]
+    Check(String),
 }
 
 #[tokio::main]
@@ -49,80 +369,583 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
         .enable_http1()
         .build();
 
-    let client = Client::builder().build::<_, hyper::Body>(https);
+    let (shutdown_tx, _) = broadcast::channel::<()>(1);
+    let (trigger_tx, _) = broadcast::channel::<String>(16);
+
+    let addresses: Vec<AddressCheck> = config
+        .addresses
+        .clone()
+        .into_iter()
+        .map(|entry| AddressCheck::from_config(entry.into_config(), &config))
+        .collect();
+
+    let state: SharedState = Arc::new(Mutex::new(
+        addresses
+            .iter()
+            .map(|address| (address.url.clone(), CheckState::default()))
+            .collect(),
+    ));
+    let mute: SharedMute = Arc::new(Mutex::new(MuteState::default()));
 
     let mut handles = vec![];
-    let address = config.addresses.clone();
 
-    for u in address {
+    if let Some(proxy) = config.proxy.clone() {
+        let proxy_addr: Uri = proxy
+            .address
+            .parse()
+            .expect("proxy.address must be a valid URI");
+
+        let auth = match (proxy.username, proxy.password) {
+            (Some(username), Some(password)) => Some(Auth::new(username, password)),
+            _ => None,
+        };
+
+        let socks = SocksConnector {
+            proxy_addr,
+            auth,
+            connector: https,
+        };
+
+        let client = Client::builder().build::<_, hyper::Body>(socks);
+        spawn_checks(
+            addresses,
+            &bot,
+            &config,
+            &client,
+            &state,
+            &mute,
+            &shutdown_tx,
+            &trigger_tx,
+            &mut handles,
+        );
+    } else {
+        let client = Client::builder().build::<_, hyper::Body>(https);
+        spawn_checks(
+            addresses,
+            &bot,
+            &config,
+            &client,
+            &state,
+            &mute,
+            &shutdown_tx,
+            &trigger_tx,
+            &mut handles,
+        );
+    }
+
+    let command_bot_shutdown = shutdown_tx.subscribe();
+    handles.push(tokio::spawn(run_command_bot(
+        bot.clone(),
+        state.clone(),
+        mute.clone(),
+        trigger_tx.clone(),
+        config.telegram_chat_id,
+        command_bot_shutdown,
+    )));
+
+    if let Some(listen) = config.listen.clone() {
+        let addr: SocketAddr = listen.parse().expect("listen must be a valid socket address");
+        let server = Server::try_bind(&addr)
+            .unwrap_or_else(|error| panic!("failed to bind metrics listener on {}: {}", addr, error));
+        let metrics_shutdown = shutdown_tx.subscribe();
+        handles.push(tokio::spawn(run_metrics_server(
+            server,
+            state.clone(),
+            metrics_shutdown,
+        )));
+    }
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {
+            info!("Received SIGINT, shutting down");
+        }
+        _ = sigterm.recv() => {
+            info!("Received SIGTERM, shutting down");
+        }
+    }
+    let _ = shutdown_tx.send(());
+
+    futures::future::join_all(handles).await;
+
+    let summary = status_report(&state);
+    if let Err(error) = bot
+        .send_message(
+            ChatId(config.telegram_chat_id),
+            format!("Healthcheck shutting down, final counts:\n{}", summary),
+        )
+        .send()
+        .await
+    {
+        error!("telegram error while sending shutdown summary: {}", error);
+    }
+
+    Result::Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_checks<C>(
+    addresses: Vec<AddressCheck>,
+    bot: &Bot,
+    config: &Config,
+    client: &Client<C>,
+    state: &SharedState,
+    mute: &SharedMute,
+    shutdown_tx: &broadcast::Sender<()>,
+    trigger_tx: &broadcast::Sender<String>,
+    handles: &mut Vec<JoinHandle<Result<(), Box<dyn Error + Send + Sync>>>>,
+) where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    for address in addresses {
         let bot = bot.clone();
         let client = client.clone();
         let config = config.clone();
+        let state = state.clone();
+        let mute = mute.clone();
+        let shutdown_rx = shutdown_tx.subscribe();
+        let trigger_rx = trigger_tx.subscribe();
 
         handles.push(tokio::spawn(async move {
             check(
-                string_to_static_str(u.to_string()),
+                address,
                 bot.clone(),
                 config.clone(),
                 client.clone(),
+                state,
+                mute,
+                shutdown_rx,
+                trigger_rx,
             )
             .await
         }));
     }
-    futures::future::join_all(handles).await;
-    Result::Ok(())
 }
 
-async fn check<'a>(
-    url: &str,
+fn status_report(state: &SharedState) -> String {
+    let state = state.lock().unwrap();
+    let mut report = String::new();
+    for (url, s) in state.iter() {
+        let _ = writeln!(
+            report,
+            "{} {} (fail_in_row={}, success={}, failures={})",
+            url,
+            if s.up { "UP" } else { "DOWN" },
+            s.fail_in_row,
+            s.number_of_success,
+            s.number_of_fail
+        );
+    }
+    report
+}
+
+// Serves Prometheus metrics and a JSON status page over the configured
+// `listen` address, for dashboards that would rather scrape than watch Telegram.
+//
+// The caller is expected to have already bound `server` (see `Server::try_bind`
+// in `main`), so a bad `listen` address fails loudly at startup instead of
+// silently killing this task.
+async fn run_metrics_server(
+    server: hyper::server::Builder<hyper::server::conn::AddrIncoming>,
+    state: SharedState,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let make_svc = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle_metrics_request(req, &state)) }
+            }))
+        }
+    });
+
+    let graceful = server.serve(make_svc).with_graceful_shutdown(async {
+        let _ = shutdown_rx.recv().await;
+    });
+
+    graceful.await?;
+    Ok(())
+}
+
+fn handle_metrics_request(req: Request<Body>, state: &SharedState) -> Response<Body> {
+    match req.uri().path() {
+        "/metrics" => Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(render_prometheus(state)))
+            .unwrap(),
+        "/status" => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(render_status_json(state)))
+            .unwrap(),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("not found"))
+            .unwrap(),
+    }
+}
+
+// Escapes a label value per the Prometheus text exposition format:
+// backslashes, double quotes, and newlines must be escaped before being
+// placed inside a quoted label value.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn render_prometheus(state: &SharedState) -> String {
+    let state = state.lock().unwrap();
+    let mut out = String::new();
+
+    out.push_str("# HELP healthcheck_up Whether the address is currently reachable\n");
+    out.push_str("# TYPE healthcheck_up gauge\n");
+    for (url, s) in state.iter() {
+        let _ = writeln!(
+            out,
+            "healthcheck_up{{address=\"{}\"}} {}",
+            escape_label_value(url),
+            if s.up { 1 } else { 0 }
+        );
+    }
+
+    out.push_str("# HELP healthcheck_failures_total Total number of failed checks\n");
+    out.push_str("# TYPE healthcheck_failures_total counter\n");
+    for (url, s) in state.iter() {
+        let _ = writeln!(
+            out,
+            "healthcheck_failures_total{{address=\"{}\"}} {}",
+            escape_label_value(url),
+            s.number_of_fail
+        );
+    }
+
+    out.push_str("# HELP healthcheck_successes_total Total number of successful checks\n");
+    out.push_str("# TYPE healthcheck_successes_total counter\n");
+    for (url, s) in state.iter() {
+        let _ = writeln!(
+            out,
+            "healthcheck_successes_total{{address=\"{}\"}} {}",
+            escape_label_value(url),
+            s.number_of_success
+        );
+    }
+
+    out.push_str("# HELP healthcheck_last_latency_ms Latency of the most recent check in milliseconds\n");
+    out.push_str("# TYPE healthcheck_last_latency_ms gauge\n");
+    for (url, s) in state.iter() {
+        if let Some(latency) = s.last_latency_ms {
+            let _ = writeln!(
+                out,
+                "healthcheck_last_latency_ms{{address=\"{}\"}} {}",
+                escape_label_value(url),
+                latency
+            );
+        }
+    }
+
+    out
+}
+
+fn render_status_json(state: &SharedState) -> String {
+    let state = state.lock().unwrap();
+    serde_json::to_string(&*state).unwrap_or_else(|_| "{}".to_string())
+}
+
+async fn run_command_bot(
     bot: Bot,
-    config: Config,
-    client: Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>>,
+    state: SharedState,
+    mute: SharedMute,
+    trigger_tx: broadcast::Sender<String>,
+    chat_id: i64,
+    mut shutdown_rx: broadcast::Receiver<()>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let mut number_of_fail: u64 = 0;
-    let mut number_of_success: u64 = 0;
-    let mut fail_in_row: u64 = 0;
+    let handler = Update::filter_message()
+        .filter_command::<Command>()
+        .endpoint(move |bot: Bot, msg: Message, cmd: Command| {
+            let state = state.clone();
+            let mute = mute.clone();
+            let trigger_tx = trigger_tx.clone();
+            async move { handle_command(bot, msg, cmd, state, mute, trigger_tx, chat_id).await }
+        });
+
+    let mut dispatcher = Dispatcher::builder(bot, handler).build();
+
+    tokio::select! {
+        _ = dispatcher.dispatch() => {}
+        _ = shutdown_rx.recv() => {
+            info!("Stopping Telegram command dispatcher");
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_command(
+    bot: Bot,
+    msg: Message,
+    cmd: Command,
+    state: SharedState,
+    mute: SharedMute,
+    trigger_tx: broadcast::Sender<String>,
+    chat_id: i64,
+) -> ResponseResult<()> {
+    if msg.chat.id != ChatId(chat_id) {
+        return Ok(());
+    }
+
+    let reply = match cmd {
+        Command::Status => {
+            let report = status_report(&state);
+            if report.is_empty() {
+                "No addresses configured".to_string()
+            } else {
+                report
+            }
+        }
+        Command::Mute(args) => apply_mute(&mute, &args),
+        Command::Check(url) => {
+            if url == TRIGGER_ALL {
+                let _ = trigger_tx.send(url);
+                "Triggered an immediate check of every address".to_string()
+            } else if state.lock().unwrap().contains_key(&url) {
+                let _ = trigger_tx.send(url.clone());
+                format!("Triggered an immediate check of {}", url)
+            } else {
+                format!(
+                    "Unknown address `{}`; use /status to see configured addresses",
+                    url
+                )
+            }
+        }
+    };
+
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+fn apply_mute(mute: &SharedMute, args: &str) -> String {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    match parts.as_slice() {
+        [duration] => match parse_duration(duration) {
+            Some(duration) => {
+                mute.lock().unwrap().global_until = Some(Instant::now() + duration);
+                format!("Muted all notifications for {:?}", duration)
+            }
+            None => format!("Could not parse duration `{}`", duration),
+        },
+        [url, duration] => match parse_duration(duration) {
+            Some(duration) => {
+                mute.lock()
+                    .unwrap()
+                    .addresses
+                    .insert(url.to_string(), Instant::now() + duration);
+                format!("Muted {} for {:?}", url, duration)
+            }
+            None => format!("Could not parse duration `{}`", duration),
+        },
+        _ => "Usage: /mute <duration> or /mute <url> <duration>".to_string(),
+    }
+}
+
+// Parses simple durations like "30s", "10m", "2h" or "1d".
+fn parse_duration(input: &str) -> Option<Duration> {
+    let split_at = input.len().checked_sub(1)?;
+    let (value, unit) = input.split_at(split_at);
+    let value: u64 = value.parse().ok()?;
+    let seconds = match unit {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        "d" => value * 86400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod mute_tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_supports_all_suffixes() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_unknown_suffix_or_value() {
+        assert_eq!(parse_duration("10x"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn not_muted_by_default() {
+        let state = MuteState::default();
+        assert!(!state.is_muted("https://example.com"));
+    }
+
+    #[test]
+    fn global_mute_covers_every_address() {
+        let state = MuteState {
+            global_until: Some(Instant::now() + Duration::from_secs(60)),
+            addresses: HashMap::new(),
+        };
+        assert!(state.is_muted("https://example.com"));
+        assert!(state.is_muted("https://anything.example"));
+    }
+
+    #[test]
+    fn per_address_mute_only_covers_that_address() {
+        let mut addresses = HashMap::new();
+        addresses.insert(
+            "https://example.com".to_string(),
+            Instant::now() + Duration::from_secs(60),
+        );
+        let state = MuteState {
+            global_until: None,
+            addresses,
+        };
+        assert!(state.is_muted("https://example.com"));
+        assert!(!state.is_muted("https://other.example"));
+    }
+
+    #[test]
+    fn expired_mute_no_longer_applies() {
+        let mut addresses = HashMap::new();
+        addresses.insert(
+            "https://example.com".to_string(),
+            Instant::now() - Duration::from_secs(1),
+        );
+        let state = MuteState {
+            global_until: None,
+            addresses,
+        };
+        assert!(!state.is_muted("https://example.com"));
+    }
+}
+
+async fn check<C>(
+    mut address: AddressCheck,
+    bot: Bot,
+    config: Config,
+    client: Client<C>,
+    state: SharedState,
+    mute: SharedMute,
+    mut shutdown_rx: broadcast::Receiver<()>,
+    mut trigger_rx: broadcast::Receiver<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let url = address.url.as_str();
 
     match url.parse::<Uri>() {
-        Ok(uri) => loop {
+        Ok(uri) => 'checking: loop {
             let mut message: Option<String> = None;
 
-            match client.get(uri.clone()).await {
-                Result::Ok(response) if (response.status() == StatusCode::OK) => {
-                    number_of_success += 1;
+            let started = Instant::now();
 
-                    if fail_in_row > 0 {
-                        fail_in_row = 0;
-                        message = Some(format!("{} Recovered", url));
-                    } else {
-                        info!("Check {} OK", url);
+            // Resolve the outcome of this probe, including any async body
+            // read, before touching the shared state lock - it must never be
+            // held across an `.await`.
+            enum Outcome {
+                Success,
+                Failure(String),
+            }
+
+            // The request and, when `body_match` is set, the body read that
+            // follows it are a single unit of work: `timeout` must bound both
+            // together, or a stalled body can hang the check indefinitely
+            // even though the response headers arrived in time.
+            let probe = async {
+                match client.get(uri.clone()).await {
+                    Result::Ok(response) if address.expected_status.matches(response.status()) => {
+                        match body_mismatch(&address, response).await {
+                            Ok(None) => Outcome::Success,
+                            Ok(Some(reason)) => Outcome::Failure(reason),
+                            Err(error) => {
+                                Outcome::Failure(format!("failed to read body: {}", error))
+                            }
+                        }
                     }
+                    Result::Ok(response) => {
+                        Outcome::Failure(format!("status {}", response.status()))
+                    }
+                    Result::Err(error) => Outcome::Failure(error.to_string()),
                 }
-                Result::Ok(response) => {
-                    number_of_fail += 1;
-                    fail_in_row += 1;
-                    message = Some(format!(
-                        "{}: status {}, failures: {}, succes: {}",
-                        url,
-                        response.status(),
-                        number_of_fail,
-                        number_of_success
-                    ));
-                }
-                Result::Err(error) => {
-                    number_of_fail += 1;
-                    fail_in_row += 1;
-                    message = Some(format!(
-                        "{}: {}, failures: {}, succes: {}",
-                        url, error, number_of_fail, number_of_success
-                    ));
+            };
+
+            // Race the probe against shutdown too: without this, a peer that
+            // stalls mid-body (with no `timeout` configured, or simply
+            // slower than it) would hang this task forever and block
+            // `join_all` in `main` from ever returning on SIGINT/SIGTERM.
+            let outcome = tokio::select! {
+                _ = shutdown_rx.recv() => break 'checking,
+                outcome = async {
+                    match address.timeout {
+                        Some(timeout) => match tokio::time::timeout(timeout, probe).await {
+                            Ok(outcome) => outcome,
+                            Err(_) => Outcome::Failure(format!("request timed out after {:?}", timeout)),
+                        },
+                        None => probe.await,
+                    }
+                } => outcome,
+            };
+            let latency_ms = started.elapsed().as_millis() as u64;
+
+            let fail_in_row = {
+                let mut state = state.lock().unwrap();
+                let entry = state.entry(url.to_string()).or_default();
+                entry.last_latency_ms = Some(latency_ms);
+
+                match outcome {
+                    Outcome::Success => {
+                        entry.number_of_success += 1;
+                        entry.up = true;
+
+                        if entry.fail_in_row > 0 {
+                            entry.fail_in_row = 0;
+                            message = Some(format!("{} Recovered", url));
+                        } else {
+                            info!("Check {} OK", url);
+                        }
+                    }
+                    Outcome::Failure(reason) => {
+                        entry.number_of_fail += 1;
+                        entry.fail_in_row += 1;
+                        entry.up = false;
+                        message = Some(format!(
+                            "{}: {}, failures: {}, succes: {}",
+                            url, reason, entry.number_of_fail, entry.number_of_success
+                        ));
+                    }
                 }
+
+                entry.fail_in_row
             };
 
             if message.is_none() {
-                sleep(Duration::from_millis(config.check_interval_success)).await;
+                match wait_for_next_check(
+                    address.check_interval_success.sample(&mut address.rng),
+                    &mut shutdown_rx,
+                    &mut trigger_rx,
+                    url,
+                )
+                .await
+                {
+                    WaitOutcome::Shutdown => break 'checking,
+                    WaitOutcome::Continue => {}
+                }
             } else {
-                if fail_in_row == config.notify_failures || (fail_in_row % config.rereport) == 0 {
+                if !mute.lock().unwrap().is_muted(url)
+                    && (fail_in_row == address.notify_failures
+                        || (fail_in_row % config.rereport) == 0)
+                {
                     let message = message.unwrap();
                     info!("{}", message);
                     match bot
@@ -136,7 +959,17 @@ async fn check<'a>(
                         _ => {}
                     }
                 }
-                sleep(Duration::from_millis(config.check_interval_fail)).await;
+                match wait_for_next_check(
+                    address.check_interval_fail.sample(&mut address.rng),
+                    &mut shutdown_rx,
+                    &mut trigger_rx,
+                    url,
+                )
+                .await
+                {
+                    WaitOutcome::Shutdown => break 'checking,
+                    WaitOutcome::Continue => {}
+                }
             }
         },
         Err(_) => {
@@ -147,6 +980,54 @@ async fn check<'a>(
     Ok(())
 }
 
-fn string_to_static_str(s: String) -> &'static str {
-    Box::leak(s.into_boxed_str())
+enum WaitOutcome {
+    Continue,
+    Shutdown,
+}
+
+// Waits out the given interval, but wakes early on shutdown or on an
+// on-demand `/check` trigger addressed to this URL (or to every address).
+async fn wait_for_next_check(
+    duration: Duration,
+    shutdown_rx: &mut broadcast::Receiver<()>,
+    trigger_rx: &mut broadcast::Receiver<String>,
+    url: &str,
+) -> WaitOutcome {
+    let deadline = Instant::now() + duration;
+    loop {
+        tokio::select! {
+            _ = sleep_until(deadline) => return WaitOutcome::Continue,
+            _ = shutdown_rx.recv() => return WaitOutcome::Shutdown,
+            Ok(target) = trigger_rx.recv() => {
+                if target == TRIGGER_ALL || target == url {
+                    return WaitOutcome::Continue;
+                }
+            }
+        }
+    }
+}
+
+// Downloads the response body when a body matcher is configured and checks it
+// against the expected pattern. Returns `Some(reason)` describing a mismatch,
+// or `None` when there's nothing to check or the body matches.
+async fn body_mismatch(
+    address: &AddressCheck,
+    response: hyper::Response<hyper::Body>,
+) -> Result<Option<String>, hyper::Error> {
+    let pattern = match &address.body_match {
+        Some(pattern) => pattern,
+        None => return Ok(None),
+    };
+
+    let bytes = body::to_bytes(response.into_body()).await?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    if pattern.is_match(&text) {
+        Ok(None)
+    } else {
+        Ok(Some(format!(
+            "body did not match expected pattern `{}`",
+            pattern.as_str()
+        )))
+    }
 }